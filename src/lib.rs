@@ -4,9 +4,36 @@ use crossterm::{
 };
 use std::collections::VecDeque;
 use std::error::Error;
-use std::time::{Duration, Instant};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use structopt::StructOpt;
 
+// The output format used to emit each processed measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Human,
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "human" => Ok(OutputFormat::Human),
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            other => Err(format!(
+                "unknown format '{}' (expected human, csv, json or ndjson)",
+                other
+            )),
+        }
+    }
+}
+
 // Define struct to retain settings.
 #[derive(Debug, StructOpt)]
 #[structopt(name = "yatti", about = "Yet another TapTempo implementation.")]
@@ -20,60 +47,347 @@ pub struct Config {
     /// Set the sample size needed to process the tempo.
     #[structopt(short, long, default_value = "5")]
     pub sample_size: u32,
+    /// Set the output format for each processed measurement (human, csv, json, ndjson).
+    /// csv/json/ndjson share one schema per record: timestamp_ms, filtered_interval_ms
+    /// (the outlier-rejected mean inter-hit interval, not a raw span-average), bpm
+    /// (full precision, regardless of --precision) and sample_size.
+    #[structopt(short, long, default_value = "human")]
+    pub format: OutputFormat,
 }
 
 // Run the calculations from keys hits.
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
     let mut registered_hits = RegisteredHits::new(config.sample_size as usize)?;
+    // Measurements accumulated for the `json` format, which is printed as a single array
+    // once the session ends rather than streamed record by record.
+    let mut measurements: Vec<Measurement> = Vec::new();
+
+    // Enable raw mode once for the whole polling loop below, instead of toggling it on
+    // every iteration, so no event gets dropped in the enable/disable gap.
+    terminal::enable_raw_mode()?;
+
+    // Any I/O error bails out of the loop below without losing the measurements
+    // accumulated so far for the `json` format; it is returned once they are flushed.
+    let mut io_error: Option<Box<dyn Error>> = None;
 
     // Read key hits continuously until user hits 'q' or 'Esc'.
     loop {
-        // Enable raw mode to directly receive user inputs rather than line-buffered.
-        terminal::enable_raw_mode()?;
-        // Block waiting for any event.
-        match event::read()? {
-            // Filter event to keep only key hits events.
-            Event::Key(event) => match event.code {
-                // Check which key was hit.
-                KeyCode::Char(c) => match c {
-                    // Quit if 'q' was hit.
-                    'q' => break,
-                    // Register an hit for any other character key (including space).
-                    _ => registered_hits.new_hit(),
+        // Wait for an event for up to reset_time seconds before giving up on it.
+        let has_event = match event::poll(Duration::from_secs(config.reset_time as u64)) {
+            Ok(has_event) => has_event,
+            Err(e) => {
+                io_error = Some(Box::new(e));
+                break;
+            }
+        };
+
+        if has_event {
+            // Block waiting for the event we just polled for.
+            let event = match event::read() {
+                Ok(event) => event,
+                Err(e) => {
+                    io_error = Some(Box::new(e));
+                    break;
+                }
+            };
+
+            match event {
+                // Filter event to keep only key hits events.
+                Event::Key(event) => match event.code {
+                    // Check which key was hit.
+                    KeyCode::Char(c) => match c {
+                        // Quit if 'q' was hit.
+                        'q' => break,
+                        // Register an hit for any other character key (including space).
+                        _ => registered_hits.new_hit(),
+                    },
+                    // Also register an hit on 'Enter' key.
+                    KeyCode::Enter => registered_hits.new_hit(),
+                    // Quit if 'Esc' was hit (easier for beginners, battle-tested on beloved Mom).
+                    KeyCode::Esc => break,
+                    // Continue looping on any other non-character key hit.
+                    _ => continue,
                 },
-                // Also register an hit on 'Enter' key.
-                KeyCode::Enter => registered_hits.new_hit(),
-                // Quit if 'Esc' was hit (easier for beginners, battle-tested on beloved Mom).
-                KeyCode::Esc => break,
-                // Continue looping on any other non-character key hit.
+                // Continue looping on any other non-key event (such as resizing or mouse).
                 _ => continue,
-            },
-            // Continue looping on any other non-key event (such as resizing or mouse).
-            _ => continue,
-        }
-        // Disable raw mode to display processing infos to user.
-        terminal::disable_raw_mode()?;
-
-        // Display tempo information to the user.
-        if let Some(duration) = registered_hits.next() {
-            println!(
-                "[TEMPO] {:.precision$} BPM",
-                process_tempo(duration),
-                precision = config.precision as usize
-            )
+            }
+
+            // Display tempo information to the user.
+            if let Some(stats) = registered_hits.next() {
+                let measurement = process_tempo(stats, registered_hits.hits.len());
+                if config.format == OutputFormat::Json {
+                    measurements.push(measurement);
+                } else {
+                    emit(&measurement, config.format, config.precision as usize);
+                }
+            } else if config.format == OutputFormat::Human {
+                println!("[INFO] hit any key again to run tempo processing...")
+            }
         } else {
-            println!("[INFO] hit any key again to run tempo processing...")
+            // No hit within reset_time: the tapping stopped, so start a fresh measurement
+            // rather than averaging against a stale timestamp.
+            registered_hits.reset_hits();
+            if config.format == OutputFormat::Human {
+                println!("[INFO] hit any key again to run tempo processing...")
+            }
+        }
+    }
+
+    // Disable raw mode again, since it was not disabled inside the loop anymore.
+    terminal::disable_raw_mode()?;
+
+    if config.format == OutputFormat::Json {
+        emit_json_array(&measurements);
+    }
+
+    if let Some(e) = io_error {
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+// Same as `run`, but keeps the display live by redrawing the current tempo in place on
+// every tick instead of only reprinting a new line on every key hit. Requires the
+// "async-stream" cargo feature, since it pulls in crossterm's event-stream support along
+// with a small async executor to drive it.
+#[cfg(feature = "async-stream")]
+pub async fn run_stream(config: Config) -> Result<(), Box<dyn Error>> {
+    use crossterm::event::{Event as CtEvent, EventStream, KeyCode as CtKeyCode};
+    use futures::{future::FutureExt, select, StreamExt};
+    use futures_timer::Delay;
+
+    let mut registered_hits = RegisteredHits::new(config.sample_size as usize)?;
+    let mut reader = EventStream::new();
+    let reset_time = Duration::from_secs(config.reset_time as u64);
+    let mut idle_since = Instant::now();
+    // Measurements accumulated for the `json` format, which is printed as a single array
+    // once the session ends rather than streamed record by record.
+    let mut measurements: Vec<Measurement> = Vec::new();
+    // Any I/O error bails out of the loop below without losing the measurements
+    // accumulated so far for the `json` format; it is returned once they are flushed.
+    let mut io_error: Option<Box<dyn Error>> = None;
+
+    terminal::enable_raw_mode()?;
+
+    // Read key hits continuously until user hits 'q' or 'Esc', redrawing on every tick
+    // even while the user holds a steady tempo without a fresh key hit to react to.
+    loop {
+        let mut tick = Delay::new(Duration::from_millis(200)).fuse();
+        let mut next_event = reader.next().fuse();
+        let mut new_hit = false;
+
+        select! {
+            // React to the next key event as soon as it comes in.
+            maybe_event = next_event => match maybe_event {
+                Some(Ok(CtEvent::Key(key))) => match key.code {
+                    // Quit if 'q' or 'Esc' was hit.
+                    CtKeyCode::Char('q') | CtKeyCode::Esc => break,
+                    // Register an hit for any other character key (including space), or 'Enter'.
+                    CtKeyCode::Char(_) | CtKeyCode::Enter => {
+                        registered_hits.new_hit();
+                        idle_since = Instant::now();
+                        new_hit = true;
+                    }
+                    // Ignore any other non-character key hit.
+                    _ => {}
+                },
+                // Ignore any other non-key event (such as resizing or mouse).
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    io_error = Some(Box::new(e));
+                    break;
+                }
+                None => break,
+            },
+            // Otherwise redraw on the periodic tick, so the display stays live.
+            _ = tick => {}
+        }
+
+        // No hit within reset_time: the tapping stopped, so start a fresh measurement
+        // rather than averaging against a stale timestamp, same as the synchronous `run`.
+        if idle_since.elapsed() >= reset_time {
+            registered_hits.reset_hits();
+            idle_since = Instant::now();
+        }
+
+        if let Err(e) = redraw(&mut registered_hits, &config, new_hit, &mut measurements) {
+            io_error = Some(e);
+            break;
         }
     }
 
-    // Disable raw mode again, since it was not disabled by breaking the loop to exit.
     terminal::disable_raw_mode()?;
 
+    if config.format == OutputFormat::Json {
+        emit_json_array(&measurements);
+    }
+
+    if let Some(e) = io_error {
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+// Redraw the current tempo estimate in place, overwriting the previous line. Only the
+// human format can be redrawn in place; other formats append one record per actual new
+// hit instead, same as the synchronous `run`, since overwriting would break a
+// machine-readable stream and redrawing on every tick would flood it with stale repeats.
+#[cfg(feature = "async-stream")]
+fn redraw(
+    registered_hits: &mut RegisteredHits,
+    config: &Config,
+    new_hit: bool,
+    measurements: &mut Vec<Measurement>,
+) -> Result<(), Box<dyn Error>> {
+    use crossterm::cursor::MoveToColumn;
+    use crossterm::terminal::{Clear, ClearType};
+    use crossterm::QueueableCommand;
+    use std::io::{stdout, Write};
+
+    if config.format != OutputFormat::Human {
+        if new_hit {
+            if let Some(stats) = registered_hits.next() {
+                let measurement = process_tempo(stats, registered_hits.hits.len());
+                if config.format == OutputFormat::Json {
+                    measurements.push(measurement);
+                } else {
+                    emit(&measurement, config.format, config.precision as usize);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let mut stdout = stdout();
+    stdout
+        .queue(MoveToColumn(0))?
+        .queue(Clear(ClearType::CurrentLine))?;
+
+    if let Some(stats) = registered_hits.next() {
+        let measurement = process_tempo(stats, registered_hits.hits.len());
+        write!(
+            stdout,
+            "{}",
+            format_human(&measurement, config.precision as usize)
+        )?;
+    } else {
+        write!(stdout, "[INFO] hit any key again to run tempo processing...")?;
+    }
+
+    stdout.flush()?;
+
     Ok(())
 }
-// Process the tempo in BPM unit.
-fn process_tempo(duration: Duration) -> f64 {
-    (1_f64 / duration.as_secs_f64()) * 60_f64
+
+// A fully processed tempo measurement, ready to be emitted in any supported format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Measurement {
+    timestamp: SystemTime,
+    interval: Duration,
+    bpm: f64,
+    jitter: f64,
+    stability: f64,
+    sample_size: usize,
+}
+
+// Process the robust tempo stats into a measurement in BPM unit.
+fn process_tempo(stats: TempoStats, sample_size: usize) -> Measurement {
+    let bpm = bpm_from_interval(stats.interval);
+    // Derive a jitter figure in BPM from the standard deviation of the surviving
+    // intervals, so the user can see how steady their tapping is.
+    let jitter = stats
+        .interval
+        .checked_sub(stats.jitter)
+        .filter(|faster| !faster.is_zero())
+        .map_or(0_f64, |faster| bpm_from_interval(faster) - bpm);
+
+    Measurement {
+        timestamp: SystemTime::now(),
+        interval: stats.interval,
+        bpm,
+        jitter,
+        stability: stats.stability,
+        sample_size,
+    }
+}
+
+// Convert a single interval into a tempo in BPM unit.
+fn bpm_from_interval(interval: Duration) -> f64 {
+    (1_f64 / interval.as_secs_f64()) * 60_f64
+}
+
+// Milliseconds since the Unix epoch, for machine-readable formats.
+fn timestamp_ms(measurement: &Measurement) -> f64 {
+    measurement
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+        * 1_000_f64
+}
+
+// The mean inter-hit interval once outlier intervals have been rejected (see
+// `RegisteredHits::next`) — not the raw, unfiltered average.
+fn filtered_interval_ms(measurement: &Measurement) -> f64 {
+    measurement.interval.as_secs_f64() * 1_000_f64
+}
+
+// Render a measurement the way a human reads it, honoring the configured precision.
+fn format_human(measurement: &Measurement, precision: usize) -> String {
+    format!(
+        "[TEMPO] {:.precision$} BPM (±{:.1}, {:.0}% stable)",
+        measurement.bpm,
+        measurement.jitter,
+        measurement.stability * 100_f64,
+        precision = precision
+    )
+}
+
+// Render a measurement as a single, header-less CSV row (no trailing newline), in the
+// column order `timestamp_ms,filtered_interval_ms,bpm,sample_size` — same fields, same
+// order and same names as `format_json_object` below, so both formats share one schema.
+// Machine-readable formats always carry the full-precision BPM, regardless of the
+// display `precision` setting, so a session can be charted without losing resolution.
+fn format_csv(measurement: &Measurement) -> String {
+    format!(
+        "{:.3},{:.3},{},{}",
+        timestamp_ms(measurement),
+        filtered_interval_ms(measurement),
+        measurement.bpm,
+        measurement.sample_size
+    )
+}
+
+// Render a measurement as a single JSON object (no trailing newline).
+fn format_json_object(measurement: &Measurement) -> String {
+    format!(
+        "{{\"timestamp_ms\":{:.3},\"filtered_interval_ms\":{:.3},\"bpm\":{},\"sample_size\":{}}}",
+        timestamp_ms(measurement),
+        filtered_interval_ms(measurement),
+        measurement.bpm,
+        measurement.sample_size
+    )
+}
+
+// Emit a single measurement in its streaming output format. `json` is deliberately not
+// handled here: concatenated bare objects are not a valid JSON document (unlike ndjson),
+// so it is accumulated instead and printed as a single array by `emit_json_array` once
+// the session ends.
+fn emit(measurement: &Measurement, format: OutputFormat, precision: usize) {
+    match format {
+        OutputFormat::Human => println!("{}", format_human(measurement, precision)),
+        OutputFormat::Csv => println!("{}", format_csv(measurement)),
+        OutputFormat::Ndjson => println!("{}", format_json_object(measurement)),
+        OutputFormat::Json => {}
+    }
+}
+
+// Print every measurement gathered during the session as a single well-formed JSON array.
+fn emit_json_array(measurements: &[Measurement]) {
+    let objects: Vec<String> = measurements.iter().map(format_json_object).collect();
+    println!("[{}]", objects.join(","));
 }
 
 #[derive(Debug, PartialEq)]
@@ -108,23 +422,76 @@ impl RegisteredHits {
     }
 }
 
+// Robust average of the inter-hit intervals, along with how consistent they were.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TempoStats {
+    interval: Duration,
+    jitter: Duration,
+    stability: f64,
+}
+
 impl Iterator for RegisteredHits {
-    type Item = Duration;
+    type Item = TempoStats;
 
-    // Yield the duration since the two last key hits.
+    // Yield a robust average of the durations between consecutive key hits, rejecting
+    // abnormal intervals rather than letting a single mistimed tap skew the whole reading.
     fn next(&mut self) -> Option<Self::Item> {
         if self.hits.len() <= 1 {
-            None
+            return None;
+        }
+
+        // Compute each individual inter-hit interval, in seconds.
+        let intervals: Vec<f64> = self
+            .hits
+            .iter()
+            .zip(self.hits.iter().skip(1))
+            .map(|(previous, current)| current.duration_since(*previous).as_secs_f64())
+            .collect();
+
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+        let std_dev = |values: &[f64], mean: f64| {
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+        };
+
+        let raw_mean = mean(&intervals);
+
+        // With fewer than three intervals there is nothing meaningful to reject outliers
+        // against, so fall back to the plain mean.
+        let kept: Vec<f64> = if intervals.len() < 3 {
+            intervals
         } else {
-            // Process the elapsed time between key hits.
-            let duration = self
-                .hits
-                .back()?
-                .duration_since(*self.hits.front()?)
-                .checked_div(self.hits.len() as u32 - 1);
+            let raw_std_dev = std_dev(&intervals, raw_mean);
+            let lower = raw_mean - 2_f64 * raw_std_dev;
+            let upper = raw_mean + 2_f64 * raw_std_dev;
+            let survivors: Vec<f64> = intervals
+                .iter()
+                .copied()
+                .filter(|interval| *interval >= lower && *interval <= upper)
+                .collect();
+            if survivors.is_empty() {
+                intervals
+            } else {
+                survivors
+            }
+        };
 
-            duration
+        let clean_mean = mean(&kept);
+        // Guard against division by zero, which happens when every kept interval is zero,
+        // i.e. when the hits they span landed on the exact same instant.
+        if clean_mean <= 0_f64 {
+            return None;
         }
+        let clean_std_dev = std_dev(&kept, clean_mean);
+
+        // Clamp to [0, 1]: very erratic tapping can push the standard deviation past the
+        // mean, which would otherwise report a nonsensical negative stability.
+        let stability = (1_f64 - (clean_std_dev / clean_mean)).clamp(0_f64, 1_f64);
+
+        Some(TempoStats {
+            interval: Duration::from_secs_f64(clean_mean),
+            jitter: Duration::from_secs_f64(clean_std_dev),
+            stability,
+        })
     }
 }
 
@@ -132,6 +499,53 @@ impl Iterator for RegisteredHits {
 mod tests {
     use super::*;
 
+    #[test]
+    fn output_format_from_str_ok() {
+        assert_eq!("human".parse(), Ok(OutputFormat::Human));
+        assert_eq!("HUMAN".parse(), Ok(OutputFormat::Human));
+        assert_eq!("csv".parse(), Ok(OutputFormat::Csv));
+        assert_eq!("json".parse(), Ok(OutputFormat::Json));
+        assert_eq!("ndjson".parse(), Ok(OutputFormat::Ndjson));
+    }
+
+    #[test]
+    fn output_format_from_str_ko_unknown() {
+        let format: Result<OutputFormat, String> = "yaml".parse();
+        assert!(format.is_err());
+    }
+
+    fn sample_measurement() -> Measurement {
+        Measurement {
+            timestamp: UNIX_EPOCH + Duration::from_secs(1),
+            interval: Duration::from_millis(500),
+            bpm: 120.123_456,
+            jitter: 1.4,
+            stability: 0.97,
+            sample_size: 5,
+        }
+    }
+
+    #[test]
+    fn format_human_ok() {
+        let rendered = format_human(&sample_measurement(), 1);
+        assert_eq!(rendered, "[TEMPO] 120.1 BPM (±1.4, 97% stable)");
+    }
+
+    #[test]
+    fn format_csv_ok_full_precision_bpm() {
+        let rendered = format_csv(&sample_measurement());
+        assert_eq!(rendered, "1000.000,500.000,120.123456,5");
+    }
+
+    #[test]
+    fn format_json_object_ok_full_precision_bpm() {
+        let rendered = format_json_object(&sample_measurement());
+        assert_eq!(
+            rendered,
+            "{\"timestamp_ms\":1000.000,\"filtered_interval_ms\":500.000,\"bpm\":120.123456,\"sample_size\":5}"
+        );
+    }
+
     #[test]
     fn registered_hits_ok_sample_size() {
         let registered_hits = RegisteredHits::new(10).unwrap();
@@ -202,9 +616,77 @@ mod tests {
         for _ in 0..9 {
             registered_hits.new_hit();
             thread::sleep(Duration::from_millis(50));
-            let duration = registered_hits.next().unwrap();
-            assert_eq!(duration.as_millis(), Duration::from_millis(50).as_millis());
+            let stats = registered_hits.next().unwrap();
+            // Real wall-clock sleeps jitter a millisecond or two either way, and the
+            // outlier-rejecting mean can land on either side of a truncating millisecond
+            // boundary, so assert a tolerance band rather than exact equality.
+            let millis = stats.interval.as_millis();
+            assert!((48..=52).contains(&millis), "got {}ms", millis);
+        }
+    }
+
+    #[test]
+    fn registered_hits_iter_next_ok_rejects_outlier() {
+        let mut registered_hits = RegisteredHits::new(11).unwrap();
+        let base = Instant::now();
+        // Nine regular 50ms intervals, then one 500ms outlier that should be rejected.
+        let offsets_ms = [0, 50, 100, 150, 200, 250, 300, 350, 400, 450, 950];
+        for offset in offsets_ms.iter() {
+            registered_hits
+                .hits
+                .push_back(base + Duration::from_millis(*offset));
+        }
+
+        let stats = registered_hits.next().unwrap();
+        assert_eq!(stats.interval.as_millis(), 50);
+        assert_eq!(stats.jitter.as_millis(), 0);
+        assert!((stats.stability - 1_f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn registered_hits_iter_next_ok_skips_rejection_with_lt_3_intervals() {
+        let mut registered_hits = RegisteredHits::new(3).unwrap();
+        let base = Instant::now();
+        // Only two intervals (50ms then 1000ms): too few to reject outliers against, so
+        // the plain mean (525ms) must be returned untouched.
+        let offsets_ms = [0, 50, 1050];
+        for offset in offsets_ms.iter() {
+            registered_hits
+                .hits
+                .push_back(base + Duration::from_millis(*offset));
+        }
+
+        let stats = registered_hits.next().unwrap();
+        let millis = stats.interval.as_millis();
+        assert!((524..=526).contains(&millis), "got {}ms", millis);
+    }
+
+    #[test]
+    fn registered_hits_iter_next_ok_stable_with_identical_intervals() {
+        let mut registered_hits = RegisteredHits::new(5).unwrap();
+        let base = Instant::now();
+        for offset in [0, 50, 100, 150, 200].iter() {
+            registered_hits
+                .hits
+                .push_back(base + Duration::from_millis(*offset));
+        }
+
+        let stats = registered_hits.next().unwrap();
+        assert_eq!(stats.jitter, Duration::from_secs(0));
+        assert!((stats.stability - 1_f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn registered_hits_iter_next_ko_with_zero_interval() {
+        let mut registered_hits = RegisteredHits::new(5).unwrap();
+        let now = Instant::now();
+        // Every hit landed on the exact same instant: the mean interval is zero, which
+        // must not be divided by.
+        for _ in 0..5 {
+            registered_hits.hits.push_back(now);
         }
+
+        assert!(registered_hits.next().is_none());
     }
 
     #[test]
@@ -1,18 +1,31 @@
 use std::process;
 use structopt::StructOpt;
-use yatti::Config;
+use yatti::{Config, OutputFormat};
 
 fn main() {
     // Parse provided arguments.
     let options = Config::from_args();
+    // Only greet the user in human mode, so machine-readable formats aren't polluted
+    // with lines that are not part of the selected format.
+    let human = options.format == OutputFormat::Human;
 
-    println!("Hit any key (but q) in cadence (q to quit).");
+    if human {
+        println!("Hit any key (but q) in cadence (q to quit).");
+    }
+
+    // Run the tempo calculator, live-refreshing the display when built with the
+    // async-stream feature, or printing it line by line on every hit otherwise.
+    #[cfg(feature = "async-stream")]
+    let result = futures::executor::block_on(yatti::run_stream(options));
+    #[cfg(not(feature = "async-stream"))]
+    let result = yatti::run(options);
 
-    // Run the tempo calculator.
-    if let Err(e) = yatti::run(options) {
+    if let Err(e) = result {
         eprintln!("[ERROR] {}", e);
         process::exit(1);
     }
 
-    println!("Goodbye!");
+    if human {
+        println!("Goodbye!");
+    }
 }